@@ -0,0 +1,657 @@
+// Pure, dependency-free ed25519 signature verification (RFC 8032).
+//
+// This only implements verification. Signing is not done in-VM: the
+// `SIGN` opcode hands the message digest to `SideEffectEngine::sign`,
+// which is expected to hold (or reach) the private key, the way a
+// Solana transaction is signed by a wallet rather than by the runtime
+// that later verifies it.
+
+type Gf = [i64; 16];
+
+const GF0: Gf = [0; 16];
+const GF1: Gf = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+const D: Gf = [
+    0x78a3, 0x1359, 0x4dca, 0x75eb, 0xd8ab, 0x4141, 0x0a4d, 0x0070,
+    0xe898, 0x7779, 0x4079, 0x8cc7, 0xfe73, 0x2b6f, 0x6cee, 0x5203,
+];
+const D2: Gf = [
+    0xf159, 0x26b2, 0x9b94, 0xebd6, 0xb156, 0x8283, 0x149a, 0x00e0,
+    0xd130, 0xeef3, 0x80f2, 0x198e, 0xfce7, 0x56df, 0xd9dc, 0x2406,
+];
+const X: Gf = [
+    0xd51a, 0x8f25, 0x2d60, 0xc956, 0xa7b2, 0x9525, 0xc760, 0x692c,
+    0xdc5c, 0xfdd6, 0xe231, 0xc0a4, 0x53fe, 0xcd6e, 0x36d3, 0x2169,
+];
+const Y: Gf = [
+    0x6658, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666,
+    0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666,
+];
+const I: Gf = [
+    0xa0b0, 0x4a0e, 0x1b27, 0xc4ee, 0xe478, 0xad2f, 0x1806, 0x2f43,
+    0xd7a7, 0x3dfb, 0x0099, 0x2b4d, 0xdf0b, 0x4fc1, 0x2480, 0x2b83,
+];
+
+fn car25519(o: &mut Gf) {
+    let mut c: i64;
+    for i in 0..16 {
+        o[i] += 1 << 16;
+        c = o[i] >> 16;
+        o[(i + 1) * ((i < 15) as usize)] += c - 1 + 37 * (c - 1) * ((i == 15) as i64);
+        o[i] -= c << 16;
+    }
+}
+
+fn sel25519(p: &mut Gf, q: &mut Gf, b: i64) {
+    let c = !(b - 1);
+    for i in 0..16 {
+        let t = c & (p[i] ^ q[i]);
+        p[i] ^= t;
+        q[i] ^= t;
+    }
+}
+
+fn pack25519(o: &mut [u8], n: &Gf) {
+    let mut m = [0i64; 16];
+    let mut t = *n;
+    for _ in 0..2 {
+        car25519(&mut t);
+    }
+    for _ in 0..2 {
+        m[0] = t[0] - 0xffed;
+        for i in 1..15 {
+            m[i] = t[i] - 0xffff - ((m[i - 1] >> 16) & 1);
+            m[i - 1] &= 0xffff;
+        }
+        m[15] = t[15] - 0x7fff - ((m[14] >> 16) & 1);
+        let b = (m[15] >> 16) & 1;
+        m[14] &= 0xffff;
+        sel25519(&mut t, &mut m, 1 - b);
+    }
+    for i in 0..16 {
+        o[2 * i] = (t[i] & 0xff) as u8;
+        o[2 * i + 1] = ((t[i] >> 8) & 0xff) as u8;
+    }
+}
+
+fn neq25519(a: &Gf, b: &Gf) -> bool {
+    let mut c = [0u8; 32];
+    let mut d = [0u8; 32];
+    pack25519(&mut c, a);
+    pack25519(&mut d, b);
+    c != d
+}
+
+fn par25519(a: &Gf) -> i64 {
+    let mut d = [0u8; 32];
+    pack25519(&mut d, a);
+    (d[0] & 1) as i64
+}
+
+fn unpack25519(o: &mut Gf, n: &[u8]) {
+    for i in 0..16 {
+        o[i] = n[2 * i] as i64 + ((n[2 * i + 1] as i64) << 8);
+    }
+    o[15] &= 0x7fff;
+}
+
+fn gf_add(o: &mut Gf, a: &Gf, b: &Gf) {
+    for i in 0..16 {
+        o[i] = a[i] + b[i];
+    }
+}
+
+fn gf_sub(o: &mut Gf, a: &Gf, b: &Gf) {
+    for i in 0..16 {
+        o[i] = a[i] - b[i];
+    }
+}
+
+fn gf_mul(o: &mut Gf, a: &Gf, b: &Gf) {
+    let mut t = [0i64; 31];
+    for i in 0..16 {
+        for j in 0..16 {
+            t[i + j] += a[i] * b[j];
+        }
+    }
+    for i in 0..15 {
+        t[i] += 38 * t[i + 16];
+    }
+    let mut o_tmp = [0i64; 16];
+    o_tmp.copy_from_slice(&t[0..16]);
+    for _ in 0..2 {
+        car25519(&mut o_tmp);
+    }
+    *o = o_tmp;
+}
+
+fn gf_sq(o: &mut Gf, a: &Gf) {
+    let a_copy = *a;
+    gf_mul(o, &a_copy, &a_copy);
+}
+
+fn inv25519(o: &mut Gf, i_: &Gf) {
+    let mut c = *i_;
+    for a in (0..254).rev() {
+        let c_copy = c;
+        gf_sq(&mut c, &c_copy);
+        if a != 2 && a != 4 {
+            let c_copy2 = c;
+            gf_mul(&mut c, &c_copy2, i_);
+        }
+    }
+    *o = c;
+}
+
+fn pow2523(o: &mut Gf, i_: &Gf) {
+    let mut c = *i_;
+    for a in (0..251).rev() {
+        let c_copy = c;
+        gf_sq(&mut c, &c_copy);
+        if a != 1 {
+            let c_copy2 = c;
+            gf_mul(&mut c, &c_copy2, i_);
+        }
+    }
+    *o = c;
+}
+
+type Point = [Gf; 4];
+
+fn point_add(p: &mut Point, q: &Point) {
+    let mut a = GF0;
+    let mut b = GF0;
+    let mut c = GF0;
+    let mut d = GF0;
+    let mut e = GF0;
+    let mut f = GF0;
+    let mut g = GF0;
+    let mut h = GF0;
+    let mut t = GF0;
+
+    gf_sub(&mut a, &p[1], &p[0]);
+    gf_sub(&mut t, &q[1], &q[0]);
+    let a_before = a;
+    let t_before = t;
+    gf_mul(&mut a, &a_before, &t_before);
+    gf_add(&mut b, &p[0], &p[1]);
+    gf_add(&mut t, &q[0], &q[1]);
+    let b_before = b;
+    let t_before = t;
+    gf_mul(&mut b, &b_before, &t_before);
+    gf_mul(&mut c, &p[3], &q[3]);
+    let c_before = c;
+    gf_mul(&mut c, &c_before, &D2);
+    gf_mul(&mut d, &p[2], &q[2]);
+    let d_before = d;
+    gf_add(&mut d, &d_before, &d_before);
+    gf_sub(&mut e, &b, &a);
+    gf_sub(&mut f, &d, &c);
+    gf_add(&mut g, &d, &c);
+    gf_add(&mut h, &b, &a);
+
+    gf_mul(&mut p[0], &e, &f);
+    gf_mul(&mut p[1], &h, &g);
+    gf_mul(&mut p[2], &g, &f);
+    gf_mul(&mut p[3], &e, &h);
+}
+
+fn cswap(p: &mut Point, q: &mut Point, b: u8) {
+    for i in 0..4 {
+        sel25519(&mut p[i], &mut q[i], b as i64);
+    }
+}
+
+fn point_pack(r: &mut [u8], p: &Point) {
+    let mut tx = GF0;
+    let mut ty = GF0;
+    let mut zi = GF0;
+    inv25519(&mut zi, &p[2]);
+    gf_mul(&mut tx, &p[0], &zi);
+    gf_mul(&mut ty, &p[1], &zi);
+    pack25519(r, &ty);
+    r[31] ^= (par25519(&tx) << 7) as u8;
+}
+
+fn unpackneg(r: &mut Point, p: &[u8]) -> bool {
+    // x^2 = (y^2 - 1) / (d*y^2 + 1)
+    let mut t = GF0;
+    let mut chk = GF0;
+    let mut num = GF0; // y^2 - 1
+    let mut den = GF0; // d*y^2 + 1
+    let mut den2 = GF0;
+    let mut den4 = GF0;
+    let mut den6 = GF0;
+
+    r[2] = GF1;
+    unpack25519(&mut r[1], p);
+    gf_sq(&mut t, &r[1]);
+    gf_mul(&mut den, &t, &D);
+    gf_sub(&mut num, &t, &r[2]);
+    let den_before = den;
+    gf_add(&mut den, &den_before, &r[2]);
+
+    gf_sq(&mut den2, &den);
+    gf_sq(&mut den4, &den2);
+    gf_mul(&mut den6, &den4, &den2);
+    gf_mul(&mut t, &den6, &num);
+    let t_before = t;
+    gf_mul(&mut t, &t_before, &den);
+
+    let t_before = t;
+    pow2523(&mut t, &t_before);
+    let t_before = t;
+    gf_mul(&mut t, &t_before, &num);
+    let t_before = t;
+    gf_mul(&mut t, &t_before, &den);
+    let t_before = t;
+    gf_mul(&mut t, &t_before, &den);
+    gf_mul(&mut r[0], &t, &den);
+
+    gf_sq(&mut chk, &r[0]);
+    let chk_before = chk;
+    gf_mul(&mut chk, &chk_before, &den);
+    if neq25519(&chk, &num) {
+        let r0_before = r[0];
+        gf_mul(&mut r[0], &r0_before, &I);
+    }
+
+    gf_sq(&mut chk, &r[0]);
+    let chk_before = chk;
+    gf_mul(&mut chk, &chk_before, &den);
+    if neq25519(&chk, &num) {
+        return false;
+    }
+
+    if par25519(&r[0]) == (p[31] >> 7) as i64 {
+        let r0_before = r[0];
+        gf_sub(&mut r[0], &GF0, &r0_before);
+    }
+
+    let r0_before = r[0];
+    let r1_before = r[1];
+    gf_mul(&mut r[3], &r0_before, &r1_before);
+    true
+}
+
+fn scalarmult(p: &mut Point, q: &Point, s: &[u8]) {
+    p[0] = GF0;
+    p[1] = GF1;
+    p[2] = GF1;
+    p[3] = GF0;
+    let mut q_mut = *q;
+    for i in (0..256).rev() {
+        let b = ((s[i / 8] >> (i & 7)) & 1) as u8;
+        cswap(p, &mut q_mut, b);
+        let p_copy = *p;
+        point_add(&mut q_mut, &p_copy);
+        let p_copy = *p;
+        point_add(p, &p_copy);
+        cswap(p, &mut q_mut, b);
+    }
+}
+
+// --- SHA-512, needed internally by the ed25519 signature scheme's
+// challenge hash (distinct from the blake2b digest the VM hashes
+// message nouns with before handing them to SIGN/VERIFY). ---
+
+const SHA512_K: [u64; 80] = [
+    0x428a2f98d728ae22, 0x7137449123ef65cd, 0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc,
+    0x3956c25bf348b538, 0x59f111f1b605d019, 0x923f82a4af194f9b, 0xab1c5ed5da6d8118,
+    0xd807aa98a3030242, 0x12835b0145706fbe, 0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f, 0x80deb1fe3b1696b1, 0x9bdc06a725c71235, 0xc19bf174cf692694,
+    0xe49b69c19ef14ad2, 0xefbe4786384f25e3, 0x0fc19dc68b8cd5b5, 0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275, 0x4a7484aa6ea6e483, 0x5cb0a9dcbd41fbd4, 0x76f988da831153b5,
+    0x983e5152ee66dfab, 0xa831c66d2db43210, 0xb00327c898fb213f, 0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2, 0xd5a79147930aa725, 0x06ca6351e003826f, 0x142929670a0e6e70,
+    0x27b70a8546d22ffc, 0x2e1b21385c26c926, 0x4d2c6dfc5ac42aed, 0x53380d139d95b3df,
+    0x650a73548baf63de, 0x766a0abb3c77b2a8, 0x81c2c92e47edaee6, 0x92722c851482353b,
+    0xa2bfe8a14cf10364, 0xa81a664bbc423001, 0xc24b8b70d0f89791, 0xc76c51a30654be30,
+    0xd192e819d6ef5218, 0xd69906245565a910, 0xf40e35855771202a, 0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8, 0x1e376c085141ab53, 0x2748774cdf8eeb99, 0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63, 0x4ed8aa4ae3418acb, 0x5b9cca4f7763e373, 0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc, 0x78a5636f43172f60, 0x84c87814a1f0ab72, 0x8cc702081a6439ec,
+    0x90befffa23631e28, 0xa4506cebde82bde9, 0xbef9a3f7b2c67915, 0xc67178f2e372532b,
+    0xca273eceea26619c, 0xd186b8c721c0c207, 0xeada7dd6cde0eb1e, 0xf57d4f7fee6ed178,
+    0x06f067aa72176fba, 0x0a637dc5a2c898a6, 0x113f9804bef90dae, 0x1b710b35131c471b,
+    0x28db77f523047d84, 0x32caab7b40c72493, 0x3c9ebe0a15c9bebc, 0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6, 0x597f299cfc657e2a, 0x5fcb6fab3ad6faec, 0x6c44198c4a475817,
+];
+
+fn sha512_block(h: &mut [u64; 8], block: &[u8]) {
+    let mut w = [0u64; 80];
+    for i in 0..16 {
+        w[i] = (block[i * 8] as u64) << 56
+            | (block[i * 8 + 1] as u64) << 48
+            | (block[i * 8 + 2] as u64) << 40
+            | (block[i * 8 + 3] as u64) << 32
+            | (block[i * 8 + 4] as u64) << 24
+            | (block[i * 8 + 5] as u64) << 16
+            | (block[i * 8 + 6] as u64) << 8
+            | (block[i * 8 + 7] as u64);
+    }
+    for i in 16..80 {
+        let s0 = w[i - 15].rotate_right(1) ^ w[i - 15].rotate_right(8) ^ (w[i - 15] >> 7);
+        let s1 = w[i - 2].rotate_right(19) ^ w[i - 2].rotate_right(61) ^ (w[i - 2] >> 6);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+
+    let (mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh) =
+        (h[0], h[1], h[2], h[3], h[4], h[5], h[6], h[7]);
+
+    for i in 0..80 {
+        let s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+        let ch = (e & f) ^ ((!e) & g);
+        let temp1 = hh
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(SHA512_K[i])
+            .wrapping_add(w[i]);
+        let s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(maj);
+
+        hh = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+    }
+
+    h[0] = h[0].wrapping_add(a);
+    h[1] = h[1].wrapping_add(b);
+    h[2] = h[2].wrapping_add(c);
+    h[3] = h[3].wrapping_add(d);
+    h[4] = h[4].wrapping_add(e);
+    h[5] = h[5].wrapping_add(f);
+    h[6] = h[6].wrapping_add(g);
+    h[7] = h[7].wrapping_add(hh);
+}
+
+fn sha512(parts: &[&[u8]]) -> [u8; 64] {
+    let mut h: [u64; 8] = [
+        0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+        0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,
+    ];
+
+    let total_len: u64 = parts.iter().map(|p| p.len() as u64).sum();
+    let mut buffer = Vec::with_capacity(parts.iter().map(|p| p.len()).sum::<usize>() + 256);
+    for p in parts {
+        buffer.extend_from_slice(p);
+    }
+    buffer.push(0x80);
+    while buffer.len() % 128 != 112 {
+        buffer.push(0);
+    }
+    let bit_len = total_len.wrapping_mul(8);
+    buffer.extend_from_slice(&[0u8; 8]); // high 64 bits of length: always 0 for our message sizes
+    buffer.extend_from_slice(&bit_len.to_be_bytes());
+
+    for block in buffer.chunks(128) {
+        sha512_block(&mut h, block);
+    }
+
+    let mut out = [0u8; 64];
+    for i in 0..8 {
+        out[i * 8..i * 8 + 8].copy_from_slice(&h[i].to_be_bytes());
+    }
+    out
+}
+
+// Reduce the 512-bit little-endian hash output modulo the group order L,
+// producing a 32-byte little-endian scalar. Straightforward schoolbook
+// reduction; this runs at most once or twice per verification, so it
+// does not need to be constant time or fast.
+fn reduce_scalar(input: &[u8; 64]) -> [u8; 32] {
+    const L: [u64; 4] = [
+        0x5812631a5cf5d3ed,
+        0x14def9dea2f79cd6,
+        0x0000000000000000,
+        0x1000000000000000,
+    ];
+
+    // Interpret input as a big integer (little-endian limbs of 32 bits)
+    // and repeatedly subtract multiples of L until it fits in 256 bits
+    // and is less than L. This is slow-but-simple long division by
+    // repeated shift-and-subtract, adequate for signature verification.
+    let mut num = vec![0u32; 17];
+    for i in 0..64 {
+        let limb = i / 4;
+        let shift = (i % 4) * 8;
+        num[limb] |= (input[i] as u32) << shift;
+    }
+
+    let l_u32: Vec<u32> = {
+        let mut v = vec![0u32; 8];
+        for i in 0..4 {
+            v[i * 2] = (L[i] & 0xffff_ffff) as u32;
+            v[i * 2 + 1] = (L[i] >> 32) as u32;
+        }
+        v
+    };
+
+    // Shift-and-subtract long division, from the most significant bit down.
+    let total_bits = num.len() * 32;
+    let mut remainder = vec![0u32; num.len()];
+    for bit in (0..total_bits).rev() {
+        // remainder <<= 1; bring in bit `bit` of num
+        let mut carry = ((num[bit / 32] >> (bit % 32)) & 1) as u64;
+        for limb in remainder.iter_mut() {
+            let shifted = ((*limb as u64) << 1) | carry;
+            carry = shifted >> 32;
+            *limb = shifted as u32;
+        }
+
+        // if remainder >= L (zero-extended), remainder -= L
+        let mut ge = true;
+        for i in (0..remainder.len()).rev() {
+            let lv = *l_u32.get(i).unwrap_or(&0);
+            if remainder[i] != lv {
+                ge = remainder[i] > lv;
+                break;
+            }
+        }
+        if ge {
+            let mut borrow: i64 = 0;
+            for i in 0..remainder.len() {
+                let lv = *l_u32.get(i).unwrap_or(&0) as i64;
+                let mut v = remainder[i] as i64 - lv - borrow;
+                if v < 0 {
+                    v += 1 << 32;
+                    borrow = 1;
+                } else {
+                    borrow = 0;
+                }
+                remainder[i] = v as u32;
+            }
+        }
+    }
+
+    let mut out = [0u8; 32];
+    for i in 0..8 {
+        out[i * 4..i * 4 + 4].copy_from_slice(&remainder[i].to_le_bytes());
+    }
+    out
+}
+
+/// Verifies an ed25519 signature over `message` under `pubkey`, per RFC 8032.
+pub fn verify(pubkey: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> bool {
+    let mut a = [GF0, GF1, GF1, GF0];
+    if !unpackneg(&mut a, pubkey) {
+        return false;
+    }
+
+    let r_bytes = &signature[0..32];
+    let s_bytes = &signature[32..64];
+
+    // Reject non-canonical s (must be < L).
+    let mut s = [0u8; 32];
+    s.copy_from_slice(s_bytes);
+    let reduced = reduce_scalar(&{
+        let mut padded = [0u8; 64];
+        padded[0..32].copy_from_slice(&s);
+        padded
+    });
+    if reduced != s {
+        return false;
+    }
+
+    let k_hash = sha512(&[r_bytes, pubkey, message]);
+    let k = reduce_scalar(&k_hash);
+
+    // Compute s*B + k*(-A); if it packs to R, the signature is valid.
+    let mut sb = [GF0, GF1, GF1, GF0];
+    let base = [X, Y, GF1, {
+        let mut t = GF0;
+        gf_mul(&mut t, &X, &Y);
+        t
+    }];
+    scalarmult(&mut sb, &base, &s);
+
+    let mut ka = [GF0, GF1, GF1, GF0];
+    scalarmult(&mut ka, &a, &k);
+
+    let mut sum = sb;
+    point_add(&mut sum, &ka);
+
+    let mut packed = [0u8; 32];
+    point_pack(&mut packed, &sum);
+
+    &packed[..] == r_bytes
+}
+
+#[cfg(test)]
+fn clamp(scalar: &mut [u8; 32]) {
+    scalar[0] &= 248;
+    scalar[31] &= 127;
+    scalar[31] |= 64;
+}
+
+#[cfg(test)]
+fn base_point() -> Point {
+    let mut xy = GF0;
+    gf_mul(&mut xy, &X, &Y);
+    [X, Y, GF1, xy]
+}
+
+#[cfg(test)]
+fn mul32(a: &[u8; 32], b: &[u8; 32]) -> [u8; 64] {
+    let mut acc = [0u32; 64];
+    for i in 0..32 {
+        for j in 0..32 {
+            acc[i + j] += (a[i] as u32) * (b[j] as u32);
+        }
+    }
+    let mut out = [0u8; 64];
+    let mut carry: u32 = 0;
+    for i in 0..64 {
+        let v = acc[i] + carry;
+        out[i] = (v & 0xff) as u8;
+        carry = v >> 8;
+    }
+    out
+}
+
+#[cfg(test)]
+fn add64(a: &[u8; 64], b: &[u8; 64]) -> [u8; 64] {
+    let mut out = [0u8; 64];
+    let mut carry: u16 = 0;
+    for i in 0..64 {
+        let v = a[i] as u16 + b[i] as u16 + carry;
+        out[i] = (v & 0xff) as u8;
+        carry = v >> 8;
+    }
+    out
+}
+
+// A from-scratch reference Ed25519 signer used only by tests (here and
+// in `eval`'s SIGN/VERIFY tests), so `verify` can be checked against
+// self-generated signatures too, not just a single fixed test vector.
+// Production signing always happens outside the VM (see the module doc
+// comment above) -- this never ships in a non-test build.
+#[cfg(test)]
+pub fn test_only_sign(seed: &[u8; 32], message: &[u8]) -> ([u8; 32], [u8; 64]) {
+    let h = sha512(&[seed]);
+    let mut scalar = [0u8; 32];
+    scalar.copy_from_slice(&h[0..32]);
+    clamp(&mut scalar);
+    let prefix = &h[32..64];
+
+    let base = base_point();
+    let mut a_point = [GF0, GF1, GF1, GF0];
+    scalarmult(&mut a_point, &base, &scalar);
+    let mut pubkey = [0u8; 32];
+    point_pack(&mut pubkey, &a_point);
+
+    let r_hash = sha512(&[prefix, message]);
+    let r_scalar = reduce_scalar(&r_hash);
+    let mut r_point = [GF0, GF1, GF1, GF0];
+    scalarmult(&mut r_point, &base, &r_scalar);
+    let mut r_bytes = [0u8; 32];
+    point_pack(&mut r_bytes, &r_point);
+
+    let k_hash = sha512(&[&r_bytes[..], &pubkey[..], message]);
+    let k = reduce_scalar(&k_hash);
+
+    let mut r_scalar_padded = [0u8; 64];
+    r_scalar_padded[0..32].copy_from_slice(&r_scalar);
+    let s = reduce_scalar(&add64(&mul32(&k, &scalar), &r_scalar_padded));
+
+    let mut signature = [0u8; 64];
+    signature[0..32].copy_from_slice(&r_bytes);
+    signature[32..64].copy_from_slice(&s);
+    (pubkey, signature)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn from_hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn array32(bytes: &[u8]) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        out.copy_from_slice(bytes);
+        out
+    }
+
+    fn array64(bytes: &[u8]) -> [u8; 64] {
+        let mut out = [0u8; 64];
+        out.copy_from_slice(bytes);
+        out
+    }
+
+    #[test]
+    fn rfc8032_test_1_empty_message() {
+        // RFC 8032 Section 7.1, TEST 1: the official known-answer vector
+        // for the empty message (32-byte public key, 64-byte signature).
+        let pubkey = from_hex("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a");
+        let signature = from_hex("e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b");
+        assert!(verify(&array32(&pubkey), &[][..], &array64(&signature)));
+    }
+
+    #[test]
+    fn sign_then_verify_round_trips() {
+        let seed = [7u8; 32];
+        let message = b"hoplight";
+        let (pubkey, signature) = test_only_sign(&seed, message);
+        assert!(verify(&pubkey, message, &signature));
+    }
+
+    #[test]
+    fn verify_rejects_a_tampered_message() {
+        let seed = [7u8; 32];
+        let (pubkey, signature) = test_only_sign(&seed, b"hoplight");
+        assert!(!verify(&pubkey, b"not hoplight", &signature));
+    }
+}
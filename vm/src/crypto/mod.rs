@@ -0,0 +1,2 @@
+pub mod blake2b;
+pub mod ed25519;
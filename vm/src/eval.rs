@@ -1,7 +1,13 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+
 use noun::{Noun};
 use axis::Axis;
 use math;
 use crypto::blake2b::Blake2b;
+use crypto::ed25519;
+use journal::Journal;
 use serialize::{self, SerializationError};
 use deserialize::deserialize;
 use opcode::*;
@@ -32,6 +38,68 @@ fn into_triple(noun: Noun) -> Option<(Noun, Noun, Noun)> {
     None
 }
 
+// Pulls exactly `len` bytes out of an atom for opcodes (VERIFY, CHECK_POW)
+// that deal in fixed-size keys/signatures/digests rather than bignums.
+// Short atoms are zero-padded on the right; atoms longer than `len` are
+// rejected rather than silently truncated.
+fn atom_bytes(noun: &Noun, len: usize) -> Option<Vec<u8>> {
+    let raw: Vec<u8> = match noun {
+        &Noun::ByteAtom(x) => vec![x],
+        &Noun::Atom(ref xs) => (**xs).clone(),
+        &Noun::Cell(_, _) => return None,
+    };
+    if raw.len() > len {
+        return None;
+    }
+    let mut out = raw;
+    out.resize(len, 0);
+    Some(out)
+}
+
+// Decodes Bitcoin's compact "bits" difficulty encoding into a big-endian
+// byte string: the high byte of `bits` is an exponent `e` and the low
+// three bytes are a mantissa `m`, giving `target = m * 256^(e-3)` (with
+// `m` shifted down instead of padded with zero bytes when `e <= 3`).
+// Mantissas with their sign bit set are rejected, matching Bitcoin's rule
+// that a compact target is never negative.
+fn decode_compact_bits(bits: u32) -> Result<Vec<u8>, EvalError> {
+    if bits & 0x0080_0000 != 0 {
+        return Err(EvalError::BadArgument);
+    }
+
+    let exponent = (bits >> 24) as i32;
+    let mantissa = bits & 0x007f_ffff;
+
+    if exponent <= 3 {
+        let shifted = mantissa >> (8 * (3 - exponent));
+        Ok(vec![(shifted >> 16) as u8, (shifted >> 8) as u8, shifted as u8])
+    } else {
+        let mut target = vec![(mantissa >> 16) as u8, (mantissa >> 8) as u8, mantissa as u8];
+        target.extend(vec![0u8; (exponent - 3) as usize]);
+        Ok(target)
+    }
+}
+
+// Drops leading zero bytes so two big-endian byte strings of different
+// lengths can still be compared by magnitude.
+fn trim_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let mut i = 0;
+    while i < bytes.len() - 1 && bytes[i] == 0 {
+        i += 1;
+    }
+    &bytes[i..]
+}
+
+fn be_bytes_leq(a: &[u8], b: &[u8]) -> bool {
+    let a = trim_leading_zeros(a);
+    let b = trim_leading_zeros(b);
+    if a.len() != b.len() {
+        a.len() < b.len()
+    } else {
+        a <= b
+    }
+}
+
 pub type EvalResult = Result<Noun, EvalError>;
 
 pub trait SideEffectEngine {
@@ -40,28 +108,119 @@ pub trait SideEffectEngine {
     fn load(&mut self, key: &[u8]) -> Option<Vec<u8>>;
     fn store(&mut self, key: &[u8], value: &[u8]);
     fn send(&mut self, destination: &[u8; 32], message: &[u8], local_cost: u64);
+    fn sign(&mut self, digest: &[u8; 32]) -> [u8; 64];
+    // Atomically flushes a successful evaluation's buffered `store` calls.
+    // Only ever called with everything an `eval`/`eval_parallel` call
+    // wrote, all at once -- never with a partial, still-running journal.
+    fn commit(&mut self, writes: &[(Vec<u8>, Vec<u8>)]);
+    // Enumerates every key currently in the store, so `gc::gc` has
+    // something to sweep candidates out of.
+    fn keys(&self) -> Vec<Vec<u8>>;
+    fn delete(&mut self, key: &[u8]);
+}
+
+// Below this many nodes (as counted by `estimate_cost`), a subtree's
+// sequential evaluation is assumed to be cheaper than the cost of forking
+// it onto another thread.
+const PARALLEL_COST_THRESHOLD: usize = 64;
+
+// Counts nodes (cells and atoms alike) in `noun`, stopping early once
+// `budget` is reached; used
+// only to decide whether a subtree is worth forking, so an exact count
+// isn't needed.
+fn estimate_cost(noun: &Noun, budget: usize) -> usize {
+    let mut stack = vec![noun];
+    let mut count = 0;
+    while let Some(n) = stack.pop() {
+        count += 1;
+        if count >= budget {
+            return count;
+        }
+        if let &Noun::Cell(ref a, ref b) = n {
+            stack.push(&**a);
+            stack.push(&**b);
+        }
+    }
+    count
 }
 
 struct Computation<'a, S: 'a> {
-    ticks_used: u64,
+    ticks_used: Arc<AtomicU64>,
     tick_cap: u64,
-    side_effector: &'a mut S,
+    side_effector: &'a Mutex<&'a mut S>,
+    // How many worker threads this Computation (and the subtrees it may
+    // still fork off) is allowed to spread across. 1 means "run
+    // sequentially from here down" -- this is what plain `eval` uses, so
+    // it never forks and behaves exactly as before.
+    threads_remaining: usize,
+    // Buffers STORE_BY_HASH writes for the whole evaluation (shared
+    // across forked subtrees, same as `ticks_used`) so they can be
+    // discarded together on error instead of partially landing in
+    // `side_effector`.
+    journal: Arc<Mutex<Journal>>,
 }
 
 
-impl<'a, S: SideEffectEngine> Computation<'a, S> {
+impl<'a, S: SideEffectEngine + Send> Computation<'a, S> {
+    fn charge(&self, ticks: u64) -> Result<(), EvalError> {
+        let total = self.ticks_used.fetch_add(ticks, Ordering::Relaxed) + ticks;
+        if total >= self.tick_cap {
+            Err(EvalError::TickLimitExceeded)
+        } else {
+            Ok(())
+        }
+    }
+
+    // Runs `left` and `right` (independent, pure subtrees over the same
+    // `subject`) on worker threads when there is budget to do so,
+    // otherwise evaluates them sequentially in place exactly like the
+    // non-parallel path. `Noun` is `Rc`-based and so isn't `Send`;
+    // subtrees handed to another thread are round-tripped through the
+    // same wire format `HASH`/`STORE_BY_HASH` already use, which keeps
+    // every `Noun` entirely thread-local.
+    fn fork_or_sequential(&mut self, subject: Noun, left: Noun, right: Noun) -> Result<(Noun, Noun), EvalError> {
+        if self.threads_remaining <= 1
+            || estimate_cost(&left, PARALLEL_COST_THRESHOLD) < PARALLEL_COST_THRESHOLD
+            || estimate_cost(&right, PARALLEL_COST_THRESHOLD) < PARALLEL_COST_THRESHOLD
+        {
+            let lhs = try!(self.eval_on(subject.clone(), left));
+            let rhs = try!(self.eval_on(subject, right));
+            return Ok((lhs, rhs));
+        }
+
+        let subject_bytes = try!(self.serialize(subject));
+        let left_bytes = try!(self.serialize(left));
+        let right_bytes = try!(self.serialize(right));
+
+        let right_threads = self.threads_remaining / 2;
+        let left_threads = self.threads_remaining - right_threads;
+        let side_effector = self.side_effector;
+        let ticks_used = &self.ticks_used;
+        let tick_cap = self.tick_cap;
+        let journal = &self.journal;
+
+        let (left_result, right_result) = thread::scope(|scope| {
+            let right_handle = scope.spawn(|| {
+                run_subtree(side_effector, ticks_used.clone(), tick_cap, right_threads, journal.clone(), &subject_bytes, &right_bytes)
+            });
+            let left_result = run_subtree(side_effector, ticks_used.clone(), tick_cap, left_threads, journal.clone(), &subject_bytes, &left_bytes);
+            let right_result = right_handle.join().unwrap_or(Err(EvalError::Something));
+            (left_result, right_result)
+        });
+
+        let lhs = try!(deserialize(&try!(left_result)).map_err(|_| EvalError::StorageCorrupt));
+        let rhs = try!(deserialize(&try!(right_result)).map_err(|_| EvalError::StorageCorrupt));
+        Ok((lhs, rhs))
+    }
+
     pub fn eval_on(&mut self, mut subject: Noun, mut formula: Noun) -> EvalResult {
         'tail_recurse: loop {
-            self.ticks_used += 1;
-            if self.ticks_used >= self.tick_cap {
-                return Err(EvalError::TickLimitExceeded);
-            }
+            try!(self.charge(1));
 
             let (opcode_noun, argument) = try!(formula.into_cell().ok_or(EvalError::AtomicFormula));
             if opcode_noun.is_cell() {
                 // Distribute. The opcode and argument are actually both formulas.
-                let lhs = try!(self.eval_on(subject.clone(), opcode_noun));
-                let rhs = try!(self.eval_on(subject, argument));
+                let (lhs, rhs) = try!(self.fork_or_sequential(subject, opcode_noun, argument));
                 return Ok(Noun::new_cell(lhs, rhs));
             }
 
@@ -72,8 +231,7 @@ impl<'a, S: SideEffectEngine> Computation<'a, S> {
                 LITERAL => Ok(argument),
                 RECURSE => {
                     if let Some((b, c)) = argument.into_cell() {
-                        let b_result = try!(self.eval_on(subject.clone(), b));
-                        let c_result = try!(self.eval_on(subject, c));
+                        let (b_result, c_result) = try!(self.fork_or_sequential(subject, b, c));
                         subject = b_result;
                         formula = c_result;
                         continue 'tail_recurse;
@@ -146,7 +304,7 @@ impl<'a, S: SideEffectEngine> Computation<'a, S> {
                 HASH => { // hash
                     let hash_target = try!(self.eval_on(subject, argument));
                     let buffer = try!(self.serialize(hash_target));
-                    self.ticks_used += 20 + (buffer.len() as u64);
+                    try!(self.charge(20 + (buffer.len() as u64)));
                     let mut result = [0u8; 64];
                     Blake2b::blake2b(&mut result[..], &buffer, &[][..]);
                     Ok(Noun::from_slice(&result[..]))
@@ -154,11 +312,14 @@ impl<'a, S: SideEffectEngine> Computation<'a, S> {
                 STORE_BY_HASH => { // store by hash
                     let hash_target = try!(self.eval_on(subject, argument));
                     let buffer = try!(self.serialize(hash_target));
-                    self.ticks_used += 20 + (buffer.len() as u64);
+                    try!(self.charge(20 + (buffer.len() as u64)));
                     let mut result = [0u8; 64 + 1];
                     result[0] = 1;
                     Blake2b::blake2b(&mut result[1..], &buffer, &[][..]);
-                    self.side_effector.store(&result[..], &buffer[..]);
+                    // Buffered, not written through: a later TickLimitExceeded
+                    // or MemoryExceeded in this same evaluation must not leave
+                    // this write sitting in `side_effector`.
+                    self.journal.lock().unwrap().write(result.to_vec(), buffer);
                     Ok(Noun::from_bool(true)) // TODO: It might be better to return the hash
                 }
                 RETRIEVE_BY_HASH => { // retrieve by hash
@@ -168,8 +329,17 @@ impl<'a, S: SideEffectEngine> Computation<'a, S> {
                         prefixed_hash.push(1);
                         prefixed_hash.extend(x.iter());
 
+                        // Read through the journal first so a STORE_BY_HASH
+                        // followed by a RETRIEVE_BY_HASH in the same
+                        // evaluation sees the value before it's committed.
+                        let journaled = self.journal.lock().unwrap().read(&prefixed_hash[..]).cloned();
+                        let stored = match journaled {
+                            Some(xs) => Some(xs),
+                            None => self.side_effector.lock().unwrap().load(&prefixed_hash[..]),
+                        };
+
                         // TODO: It might be better to always return a cell.
-                        if let Some(xs) = self.side_effector.load(&prefixed_hash[..]) {
+                        if let Some(xs) = stored {
                             let decoded = try!(deserialize(&xs[..]).map_err(|_| EvalError::StorageCorrupt));
                             Ok(Noun::new_cell(
                                 Noun::from_bool(true),
@@ -186,6 +356,56 @@ impl<'a, S: SideEffectEngine> Computation<'a, S> {
                 //    if let Some((b, c, d)) =
                 //}
 
+                SIGN => { // sign
+                    let message = try!(self.eval_on(subject, argument));
+                    let buffer = try!(self.serialize(message));
+                    try!(self.charge(20 + (buffer.len() as u64)));
+                    let mut digest = [0u8; 32];
+                    Blake2b::blake2b(&mut digest[..], &buffer, &[][..]);
+                    let signature = self.side_effector.lock().unwrap().sign(&digest);
+                    Ok(Noun::from_slice(&signature[..]))
+                }
+                VERIFY => { // verify
+                    let (pubkey_noun, message, signature_noun) =
+                        try!(into_triple(try!(self.eval_on(subject, argument))).ok_or(EvalError::BadArgument));
+                    let buffer = try!(self.serialize(message));
+                    try!(self.charge(20 + (buffer.len() as u64)));
+
+                    let pubkey_bytes = try!(atom_bytes(&pubkey_noun, 32).ok_or(EvalError::BadArgument));
+                    let signature_bytes = try!(atom_bytes(&signature_noun, 64).ok_or(EvalError::BadArgument));
+
+                    let mut pubkey = [0u8; 32];
+                    pubkey.copy_from_slice(&pubkey_bytes[..]);
+                    let mut signature = [0u8; 64];
+                    signature.copy_from_slice(&signature_bytes[..]);
+
+                    // SIGN never signs the raw message -- it signs the
+                    // blake2b digest of it -- so VERIFY must check the
+                    // same digest against the same signature.
+                    let mut digest = [0u8; 32];
+                    Blake2b::blake2b(&mut digest[..], &buffer, &[][..]);
+
+                    Ok(Noun::from_bool(ed25519::verify(&pubkey, &digest[..], &signature)))
+                }
+                CHECK_POW => { // proof of work check
+                    let (target_noun, payload) =
+                        try!(try!(self.eval_on(subject, argument)).into_cell().ok_or(EvalError::BadArgument));
+                    let buffer = try!(self.serialize(payload));
+                    try!(self.charge(20 + (buffer.len() as u64)));
+
+                    let target_bytes = try!(atom_bytes(&target_noun, 4).ok_or(EvalError::BadArgument));
+                    let bits = (target_bytes[0] as u32)
+                        | (target_bytes[1] as u32) << 8
+                        | (target_bytes[2] as u32) << 16
+                        | (target_bytes[3] as u32) << 24;
+                    let target = try!(decode_compact_bits(bits));
+
+                    let mut digest = [0u8; 64];
+                    Blake2b::blake2b(&mut digest[..], &buffer, &[][..]);
+
+                    Ok(Noun::from_bool(be_bytes_leq(&digest[..], &target[..])))
+                }
+
                 _ => Err(EvalError::BadOpcode(opcode)),
             };
         }
@@ -200,14 +420,81 @@ impl<'a, S: SideEffectEngine> Computation<'a, S> {
     }
 }
 
+// Deserializes a forked subtree, evaluates it with its own share of the
+// thread budget, and re-serializes the result so it can cross back over
+// the thread boundary the same way it crossed in.
+fn run_subtree<S: SideEffectEngine + Send>(
+    side_effector: &Mutex<&mut S>,
+    ticks_used: Arc<AtomicU64>,
+    tick_cap: u64,
+    threads_remaining: usize,
+    journal: Arc<Mutex<Journal>>,
+    subject_bytes: &[u8],
+    formula_bytes: &[u8],
+) -> Result<Vec<u8>, EvalError> {
+    let subject = try!(deserialize(subject_bytes).map_err(|_| EvalError::StorageCorrupt));
+    let formula = try!(deserialize(formula_bytes).map_err(|_| EvalError::StorageCorrupt));
+    let mut computation = Computation {
+        ticks_used: ticks_used,
+        tick_cap: tick_cap,
+        side_effector: side_effector,
+        threads_remaining: threads_remaining,
+        journal: journal,
+    };
+    let result = try!(computation.eval_on(subject, formula));
+    computation.serialize(result)
+}
+
+// Runs `subject`/`formula` to completion against `engine_mutex`, then
+// either commits the journal's writes (on success) or drops them (on
+// error) -- shared by `eval` and `eval_parallel` so the two entry points
+// can't drift on how/when a journal gets flushed.
+fn run_and_commit<S: SideEffectEngine + Send>(
+    engine_mutex: Mutex<&mut S>,
+    subject: Noun,
+    formula: Noun,
+    tick_cap: u64,
+    threads_remaining: usize,
+) -> EvalResult {
+    let journal = Arc::new(Mutex::new(Journal::new()));
+    let result = Computation{
+        tick_cap: tick_cap,
+        ticks_used: Arc::new(AtomicU64::new(0)),
+        side_effector: &engine_mutex,
+        threads_remaining: threads_remaining,
+        journal: journal.clone(),
+    }.eval_on(subject, formula);
+
+    if result.is_ok() {
+        let writes = journal.lock().unwrap().writes();
+        engine_mutex.into_inner().unwrap().commit(&writes[..]);
+    }
+    result
+}
+
+pub fn eval<S: SideEffectEngine + Send>(expression: Noun, side_effector: &mut S, tick_limit: u64) -> EvalResult {
+    if let Some((subject, formula)) = expression.into_cell() {
+        run_and_commit(Mutex::new(side_effector), subject, formula, tick_limit, 1)
+    } else {
+        Err(EvalError::Something)
+    }
+}
 
-pub fn eval<S: SideEffectEngine>(expression: Noun, side_effector: &mut S, tick_limit: u64) -> EvalResult {
+// Like `eval`, but the Distribute and RECURSE branches -- the VM's only
+// pure, data-independent subtrees -- may run on separate threads, up to
+// `num_threads` of them, falling back to sequential evaluation for
+// subtrees too small to be worth forking. `tick_limit` remains a hard,
+// global cap: `ticks_used` is shared via an atomic counter across every
+// worker.
+pub fn eval_parallel<S: SideEffectEngine + Send>(
+    expression: Noun,
+    side_effector: &mut S,
+    tick_limit: u64,
+    num_threads: usize,
+) -> EvalResult {
     if let Some((subject, formula)) = expression.into_cell() {
-        Computation{
-            tick_cap: tick_limit,
-            ticks_used: 0,
-            side_effector: side_effector,
-        }.eval_on(subject, formula)
+        let threads = if num_threads == 0 { 1 } else { num_threads };
+        run_and_commit(Mutex::new(side_effector), subject, formula, tick_limit, threads)
     } else {
         Err(EvalError::Something)
     }
@@ -217,9 +504,14 @@ pub fn eval<S: SideEffectEngine>(expression: Noun, side_effector: &mut S, tick_l
 mod test {
     use noun::Noun;
     use as_noun::AsNoun;
-    use eval::{eval, SideEffectEngine};
+    use eval::{eval, eval_parallel, EvalError, SideEffectEngine};
     use std::collections::HashMap;
     use opcode::*;
+    use crypto::ed25519;
+
+    // Arbitrary fixed seed for `TestSideEffectEngine::sign`'s signing
+    // keypair, so SIGN/VERIFY tests can derive the matching public key.
+    const TEST_SIGNING_SEED: [u8; 32] = [7u8; 32];
 
     struct TestSideEffectEngine {
         storage: HashMap<Vec<u8>, Vec<u8>>,
@@ -250,6 +542,21 @@ mod test {
         }
         fn send(&mut self, destination: &[u8; 32], message: &[u8], local_cost: u64) {
         }
+        fn sign(&mut self, digest: &[u8; 32]) -> [u8; 64] {
+            let (_, signature) = ed25519::test_only_sign(&TEST_SIGNING_SEED, digest);
+            signature
+        }
+        fn commit(&mut self, writes: &[(Vec<u8>, Vec<u8>)]) {
+            for &(ref key, ref value) in writes {
+                self.storage.insert(key.clone(), value.clone());
+            }
+        }
+        fn keys(&self) -> Vec<Vec<u8>> {
+            self.storage.keys().cloned().collect()
+        }
+        fn delete(&mut self, key: &[u8]) {
+            self.storage.remove(key);
+        }
     }
 
     fn expect_eval_with<E: AsNoun, R: AsNoun>(engine: &mut TestSideEffectEngine, expression: E, result: R) {
@@ -320,6 +627,57 @@ mod test {
         );
     }
 
+    #[test]
+    fn distribute_parallel() {
+        // Same program as `distribute`, but run through the forking
+        // evaluator. The subtrees here are far too small to actually be
+        // forked onto other threads, but the result should be identical
+        // either way.
+        let mut engine = TestSideEffectEngine::new();
+        let result = eval_parallel(
+            (22, (4, (0, 1)), (0, 1), (1, 50)).as_noun(),
+            &mut engine,
+            1000000,
+            4,
+        );
+        assert_eq!(result, Ok((23, 22, 50).as_noun()));
+    }
+
+    // A long right-linked chain of cells, so its `estimate_cost` comfortably
+    // clears `PARALLEL_COST_THRESHOLD` -- used to force `fork_or_sequential`
+    // down its actual threaded path instead of the small-subtree bailout.
+    fn big_chain(depth: u8) -> Noun {
+        let mut chain = Noun::from_u8(depth);
+        for i in (0..depth).rev() {
+            chain = Noun::new_cell(Noun::from_u8(i), chain);
+        }
+        chain
+    }
+
+    #[test]
+    fn distribute_parallel_actually_forks() {
+        // Both sides of this Distribute are `(LITERAL, big_chain(...))`
+        // formulas large enough to cross `PARALLEL_COST_THRESHOLD`, so
+        // `fork_or_sequential` must run them on separate threads (serializing
+        // the subject and each subtree across the `thread::scope` boundary)
+        // rather than taking its small-subtree shortcut. The result must
+        // still match plain sequential `eval` of the same program.
+        let left_chain = big_chain(40);
+        let right_chain = big_chain(45);
+        let left = Noun::new_cell(Noun::from_u8(LITERAL), left_chain.clone());
+        let right = Noun::new_cell(Noun::from_u8(LITERAL), right_chain.clone());
+        let formula = Noun::new_cell(left, right);
+        let expression = Noun::new_cell(Noun::from_u8(0), formula);
+        let expected = Noun::new_cell(left_chain, right_chain);
+
+        let mut engine = TestSideEffectEngine::new();
+        let result = eval_parallel(expression.clone(), &mut engine, 1000000, 4);
+        assert_eq!(result, Ok(expected.clone()));
+
+        let mut sequential_engine = TestSideEffectEngine::new();
+        assert_eq!(eval(expression, &mut sequential_engine, 1000000), Ok(expected));
+    }
+
     #[test]
     fn if_true() {
         expect_eval(
@@ -362,6 +720,48 @@ mod test {
             41);
     }
 
+    #[test]
+    fn check_pow() {
+        // 0x417fffff decodes to a 65-byte target, larger than any 64-byte
+        // blake2b digest, so every payload satisfies it.
+        expect_eval(
+            (0, CHECK_POW, (LITERAL, (Noun::from_vec(vec![0xff, 0xff, 0x7f, 0x41]), 123))),
+            Noun::from_bool(true)
+        );
+
+        // A target of exactly zero (mantissa zero) can never be met.
+        expect_eval(
+            (0, CHECK_POW, (LITERAL, (Noun::from_u8(0), 123))),
+            Noun::from_bool(false)
+        );
+    }
+
+    #[test]
+    fn sign_then_verify_round_trips() {
+        // SIGN hashes its argument before asking the engine to sign the
+        // digest; VERIFY must hash the same way before checking the
+        // signature, or a signature this VM produced could never
+        // validate against this VM's own VERIFY.
+        let mut engine = TestSideEffectEngine::new();
+        let (pubkey, _) = ed25519::test_only_sign(&TEST_SIGNING_SEED, &[][..]);
+        let pubkey_noun = Noun::from_slice(&pubkey[..]);
+
+        let signature = eval((0, SIGN, (LITERAL, 42)).as_noun(), &mut engine, 1000000).unwrap();
+
+        expect_eval_with(
+            &mut engine,
+            (0, VERIFY, (LITERAL, (pubkey_noun.clone(), 42, signature.clone()))),
+            Noun::from_bool(true),
+        );
+
+        // A signature over a different message must not verify.
+        expect_eval_with(
+            &mut engine,
+            (0, VERIFY, (LITERAL, (pubkey_noun, 43, signature))),
+            Noun::from_bool(false),
+        );
+    }
+
     #[test]
     fn store_and_get() {
         let mut engine = expect_eval(
@@ -374,4 +774,34 @@ mod test {
             (0, 21));
     }
 
+    #[test]
+    fn store_then_retrieve_within_same_eval() {
+        // Unlike `store_and_get` (two separate `eval` calls, so the second
+        // one reads back from `side_effector` after the first has already
+        // committed), this drives STORE_BY_HASH and RETRIEVE_BY_HASH from a
+        // single `eval` call. The only way RETRIEVE_BY_HASH can see the
+        // value here is by reading through the still-uncommitted journal.
+        expect_eval(
+            (21, RECURSE, ((STORE_BY_HASH, (AXIS, 1)), (HASH, (AXIS, 1))), (LITERAL, RETRIEVE_BY_HASH, (AXIS, 3))),
+            (0, 21)
+        );
+    }
+
+    #[test]
+    fn store_by_hash_rolls_back_on_later_failure() {
+        // STORE_BY_HASH only buffers its write into the journal; if a
+        // later operation in the same `eval` call blows the tick limit,
+        // `run_and_commit` must see an overall `Err` and never flush that
+        // buffered write into `side_effector`.
+        let mut engine = TestSideEffectEngine::new();
+        let expensive = big_chain(200);
+        let result = eval(
+            (21, RECURSE, ((STORE_BY_HASH, (AXIS, 1)), (HASH, (LITERAL, expensive))), (LITERAL, AXIS, 3)).as_noun(),
+            &mut engine,
+            50,
+        );
+        assert_eq!(result, Err(EvalError::TickLimitExceeded));
+        assert!(engine.keys().is_empty());
+    }
+
 }
@@ -0,0 +1,154 @@
+use std::collections::HashSet;
+
+use noun::Noun;
+use deserialize::deserialize;
+use eval::SideEffectEngine;
+
+// STORE_BY_HASH keys (and, by convention, the atoms programs embed to
+// link one stored noun to another) are a leading `1` byte followed by a
+// 64-byte blake2b digest.
+fn stored_key(digest: &[u8; 64]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(65);
+    key.push(1);
+    key.extend_from_slice(&digest[..]);
+    key
+}
+
+fn looks_like_stored_hash_key(key: &[u8]) -> bool {
+    key.len() == 65 && key[0] == 1
+}
+
+// Walks `noun` with an explicit stack, rather than recursing, so a long
+// chain of cells can't blow the native stack. Returns every atom shaped
+// like a stored-hash reference.
+fn find_references(noun: &Noun) -> Vec<Vec<u8>> {
+    let mut references = Vec::new();
+    let mut stack = vec![noun];
+    while let Some(n) = stack.pop() {
+        match n {
+            &Noun::Cell(ref a, ref b) => {
+                stack.push(&**a);
+                stack.push(&**b);
+            }
+            &Noun::Atom(ref xs) => {
+                if looks_like_stored_hash_key(&xs[..]) {
+                    references.push((**xs).clone());
+                }
+            }
+            &Noun::ByteAtom(_) => {}
+        }
+    }
+    references
+}
+
+/// Sweeps every hash-addressed entry in `engine` that isn't reachable
+/// from `roots`. Reachability is found by deserializing each stored
+/// blob, collecting any atom that looks like a stored-hash reference
+/// (see `looks_like_stored_hash_key`), and following those transitively
+/// -- the same implicit object graph `RETRIEVE_BY_HASH` lets a program
+/// walk one hop at a time.
+pub fn gc<S: SideEffectEngine>(engine: &mut S, roots: &[[u8; 64]]) {
+    let mut visited: HashSet<Vec<u8>> = HashSet::new();
+    let mut stack: Vec<Vec<u8>> = roots.iter().map(stored_key).collect();
+
+    while let Some(key) = stack.pop() {
+        if !visited.insert(key.clone()) {
+            continue;
+        }
+
+        if let Some(blob) = engine.load(&key) {
+            if let Ok(noun) = deserialize(&blob) {
+                for reference in find_references(&noun) {
+                    if !visited.contains(&reference) {
+                        stack.push(reference);
+                    }
+                }
+            }
+        }
+    }
+
+    let garbage: Vec<Vec<u8>> = engine.keys().into_iter()
+        .filter(|key| looks_like_stored_hash_key(key) && !visited.contains(key))
+        .collect();
+
+    for key in garbage {
+        engine.delete(&key);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::gc;
+    use std::collections::HashMap;
+    use noun::Noun;
+    use serialize::serialize;
+    use crypto::blake2b::Blake2b;
+    use eval::SideEffectEngine;
+
+    struct TestEngine {
+        storage: HashMap<Vec<u8>, Vec<u8>>,
+    }
+
+    impl TestEngine {
+        fn new() -> TestEngine {
+            TestEngine { storage: HashMap::new() }
+        }
+    }
+
+    impl SideEffectEngine for TestEngine {
+        fn nearest_neighbor(&mut self, _near: &[u8; 32]) -> [u8; 32] { [0u8; 32] }
+        fn random(&mut self, _dest: &mut [u8]) {}
+        fn load(&mut self, key: &[u8]) -> Option<Vec<u8>> { self.storage.get(key).cloned() }
+        fn store(&mut self, key: &[u8], value: &[u8]) { self.storage.insert(key.into(), value.into()); }
+        fn send(&mut self, _destination: &[u8; 32], _message: &[u8], _local_cost: u64) {}
+        fn sign(&mut self, _digest: &[u8; 32]) -> [u8; 64] { [0u8; 64] }
+        fn commit(&mut self, writes: &[(Vec<u8>, Vec<u8>)]) {
+            for &(ref key, ref value) in writes {
+                self.storage.insert(key.clone(), value.clone());
+            }
+        }
+        fn keys(&self) -> Vec<Vec<u8>> { self.storage.keys().cloned().collect() }
+        fn delete(&mut self, key: &[u8]) { self.storage.remove(key); }
+    }
+
+    // Stores `noun` exactly as STORE_BY_HASH would, returning its digest.
+    fn store(engine: &mut TestEngine, noun: &Noun) -> [u8; 64] {
+        let buffer = serialize(noun, 1_000_000).unwrap();
+        let mut digest = [0u8; 64];
+        Blake2b::blake2b(&mut digest[..], &buffer, &[][..]);
+        let mut key = Vec::with_capacity(65);
+        key.push(1);
+        key.extend_from_slice(&digest[..]);
+        engine.store(&key[..], &buffer[..]);
+        digest
+    }
+
+    #[test]
+    fn sweeps_unreachable_keeps_reachable() {
+        let mut engine = TestEngine::new();
+
+        let child_digest = store(&mut engine, &Noun::from_u8(7));
+
+        let mut child_key = Vec::with_capacity(65);
+        child_key.push(1);
+        child_key.extend_from_slice(&child_digest[..]);
+        let root_noun = Noun::new_cell(Noun::from_u8(1), Noun::from_slice(&child_key[..]));
+        let root_digest = store(&mut engine, &root_noun);
+
+        let garbage_digest = store(&mut engine, &Noun::from_u8(99));
+
+        assert_eq!(engine.storage.len(), 3);
+        gc(&mut engine, &[root_digest]);
+
+        let mut root_key = Vec::with_capacity(65);
+        root_key.push(1);
+        root_key.extend_from_slice(&root_digest[..]);
+        assert!(engine.load(&root_key).is_some());
+        assert!(engine.load(&child_key).is_some());
+
+        let mut garbage_key = Vec::with_capacity(65);
+        garbage_key.push(1);
+        garbage_key.extend_from_slice(&garbage_digest[..]);
+        assert!(engine.load(&garbage_key).is_none());
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Keys;
+
+/// Buffers `store` writes made over the course of a single evaluation so
+/// they can be discarded wholesale if that evaluation later fails (e.g.
+/// `EvalError::TickLimitExceeded`, `EvalError::MemoryExceeded`), instead
+/// of leaving partial writes behind in the `SideEffectEngine`. A
+/// successful evaluation flushes the journal via
+/// `SideEffectEngine::commit` in one atomic step.
+#[derive(Default)]
+pub struct Journal {
+    writes: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+impl Journal {
+    pub fn new() -> Journal {
+        Journal { writes: HashMap::new() }
+    }
+
+    pub fn write(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.writes.insert(key, value);
+    }
+
+    pub fn read(&self, key: &[u8]) -> Option<&Vec<u8>> {
+        self.writes.get(key)
+    }
+
+    pub fn keys(&self) -> Keys<Vec<u8>, Vec<u8>> {
+        self.writes.keys()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    /// Snapshots the journal as the `(key, value)` pairs `commit` expects.
+    pub fn writes(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.writes.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use journal::Journal;
+
+    #[test]
+    fn read_through_sees_uncommitted_writes() {
+        let mut journal = Journal::new();
+        assert_eq!(journal.read(b"k"), None);
+
+        journal.write(b"k".to_vec(), b"v".to_vec());
+        assert_eq!(journal.read(b"k"), Some(&b"v".to_vec()));
+        assert_eq!(journal.keys().count(), 1);
+        assert_eq!(journal.writes(), vec![(b"k".to_vec(), b"v".to_vec())]);
+    }
+
+    #[test]
+    fn starts_empty() {
+        assert!(Journal::new().is_empty());
+    }
+}
@@ -0,0 +1,19 @@
+//! Numeric opcodes used in the low byte of a formula cell, as matched by
+//! `Computation::eval_on`.
+
+pub const AXIS: u8 = 0;
+pub const LITERAL: u8 = 1;
+pub const RECURSE: u8 = 2;
+pub const IS_CELL: u8 = 3;
+pub const INCREMENT: u8 = 4;
+pub const IS_EQUAL: u8 = 5;
+pub const IF: u8 = 6;
+pub const COMPOSE: u8 = 7;
+pub const DEFINE: u8 = 8;
+pub const CALL: u8 = 9;
+pub const HASH: u8 = 10;
+pub const STORE_BY_HASH: u8 = 11;
+pub const RETRIEVE_BY_HASH: u8 = 12;
+pub const SIGN: u8 = 13;
+pub const VERIFY: u8 = 14;
+pub const CHECK_POW: u8 = 15;
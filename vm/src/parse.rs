@@ -0,0 +1,345 @@
+//! A small textual surface syntax for `Noun` formulas, so Hoplight
+//! programs can be written, stored, and debugged as text instead of
+//! nested Rust tuples.
+//!
+//! Grammar:
+//!   expr   := atom | symbol | '[' expr+ ']'
+//!   atom   := decimal digits, or `0x` followed by hex digits
+//!   symbol := an opcode mnemonic (AXIS, LITERAL, RECURSE, IF, ...)
+//!
+//! Brackets nest right-associatively: `[a b c]` parses the same as
+//! `[a [b c]]`. `Noun`'s `Display` impl is the inverse of `parse`: byte
+//! atoms print as decimals (or, when they exactly match a known opcode's
+//! value, as that opcode's mnemonic) and cells print back out using the
+//! same flattened `[a b c]` shorthand.
+
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use noun::Noun;
+use opcode;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum ParseError {
+    UnexpectedEnd,
+    UnexpectedChar(char),
+    UnexpectedToken(String),
+    UnmatchedBracket,
+    EmptyCell,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Atom(Vec<u8>), // little-endian bytes, as Noun atoms are stored
+    Symbol(String),
+}
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(input: &'a str) -> Lexer<'a> {
+        Lexer { chars: input.chars().peekable() }
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, ParseError> {
+        loop {
+            match self.chars.peek().cloned() {
+                None => return Ok(None),
+                Some(c) if c.is_whitespace() => { self.chars.next(); }
+                Some('[') => { self.chars.next(); return Ok(Some(Token::Open)); }
+                Some(']') => { self.chars.next(); return Ok(Some(Token::Close)); }
+                Some(c) if c.is_ascii_digit() => return self.lex_number().map(Some),
+                Some(c) if c.is_alphabetic() || c == '_' => return Ok(Some(self.lex_symbol())),
+                Some(c) => return Err(ParseError::UnexpectedChar(c)),
+            }
+        }
+    }
+
+    fn lex_number(&mut self) -> Result<Token, ParseError> {
+        if self.chars.peek() == Some(&'0') {
+            let mut lookahead = self.chars.clone();
+            lookahead.next();
+            if lookahead.peek() == Some(&'x') || lookahead.peek() == Some(&'X') {
+                self.chars.next();
+                self.chars.next();
+                let mut hex = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if c.is_ascii_hexdigit() {
+                        hex.push(c);
+                        self.chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                if hex.is_empty() {
+                    return Err(ParseError::UnexpectedEnd);
+                }
+                return Ok(Token::Atom(bytes_from_hex(&hex)));
+            }
+        }
+
+        let mut decimal = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_ascii_digit() {
+                decimal.push(c);
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        Ok(Token::Atom(bytes_from_decimal(&decimal)))
+    }
+
+    fn lex_symbol(&mut self) -> Token {
+        let mut text = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_alphanumeric() || c == '_' {
+                text.push(c);
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        Token::Symbol(text)
+    }
+}
+
+fn bytes_from_decimal(digits: &str) -> Vec<u8> {
+    let mut big_endian = vec![0u8];
+    for ch in digits.chars() {
+        let digit = ch.to_digit(10).expect("lexer only emits ascii digits here") as u32;
+        let mut carry = digit;
+        for byte in big_endian.iter_mut().rev() {
+            let v = (*byte as u32) * 10 + carry;
+            *byte = (v & 0xff) as u8;
+            carry = v >> 8;
+        }
+        while carry > 0 {
+            big_endian.insert(0, (carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+    to_canonical_little_endian(big_endian)
+}
+
+fn bytes_from_hex(hex: &str) -> Vec<u8> {
+    let mut digits: Vec<u8> = hex.chars()
+        .map(|c| c.to_digit(16).expect("lexer only emits ascii hex digits here") as u8)
+        .collect();
+    if digits.len() % 2 == 1 {
+        digits.insert(0, 0);
+    }
+    let big_endian: Vec<u8> = digits.chunks(2).map(|pair| (pair[0] << 4) | pair[1]).collect();
+    to_canonical_little_endian(big_endian)
+}
+
+// Reverses a big-endian byte vector into the little-endian form `Noun`
+// atoms use, and trims the high (now trailing) zero bytes that come from
+// e.g. decimal/hex literals with leading zeros.
+fn to_canonical_little_endian(mut big_endian: Vec<u8>) -> Vec<u8> {
+    big_endian.reverse();
+    while big_endian.len() > 1 && *big_endian.last().unwrap() == 0 {
+        big_endian.pop();
+    }
+    big_endian
+}
+
+fn opcode_value(name: &str) -> Option<u8> {
+    Some(match name {
+        "AXIS" => opcode::AXIS,
+        "LITERAL" => opcode::LITERAL,
+        "RECURSE" => opcode::RECURSE,
+        "IS_CELL" => opcode::IS_CELL,
+        "INCREMENT" => opcode::INCREMENT,
+        "IS_EQUAL" => opcode::IS_EQUAL,
+        "IF" => opcode::IF,
+        "COMPOSE" => opcode::COMPOSE,
+        "DEFINE" => opcode::DEFINE,
+        "CALL" => opcode::CALL,
+        "HASH" => opcode::HASH,
+        "STORE_BY_HASH" => opcode::STORE_BY_HASH,
+        "RETRIEVE_BY_HASH" => opcode::RETRIEVE_BY_HASH,
+        "SIGN" => opcode::SIGN,
+        "VERIFY" => opcode::VERIFY,
+        "CHECK_POW" => opcode::CHECK_POW,
+        _ => return None,
+    })
+}
+
+pub fn opcode_name(value: u8) -> Option<&'static str> {
+    Some(match value {
+        opcode::AXIS => "AXIS",
+        opcode::LITERAL => "LITERAL",
+        opcode::RECURSE => "RECURSE",
+        opcode::IS_CELL => "IS_CELL",
+        opcode::INCREMENT => "INCREMENT",
+        opcode::IS_EQUAL => "IS_EQUAL",
+        opcode::IF => "IF",
+        opcode::COMPOSE => "COMPOSE",
+        opcode::DEFINE => "DEFINE",
+        opcode::CALL => "CALL",
+        opcode::HASH => "HASH",
+        opcode::STORE_BY_HASH => "STORE_BY_HASH",
+        opcode::RETRIEVE_BY_HASH => "RETRIEVE_BY_HASH",
+        opcode::SIGN => "SIGN",
+        opcode::VERIFY => "VERIFY",
+        opcode::CHECK_POW => "CHECK_POW",
+        _ => return None,
+    })
+}
+
+fn parse_expr(tokens: &[Token], pos: &mut usize) -> Result<Noun, ParseError> {
+    match tokens.get(*pos) {
+        None => Err(ParseError::UnexpectedEnd),
+        Some(&Token::Atom(ref bytes)) => {
+            *pos += 1;
+            Ok(Noun::from_vec(bytes.clone()))
+        }
+        Some(&Token::Symbol(ref name)) => {
+            *pos += 1;
+            opcode_value(name)
+                .map(Noun::ByteAtom)
+                .ok_or_else(|| ParseError::UnexpectedToken(name.clone()))
+        }
+        Some(&Token::Close) => Err(ParseError::UnexpectedToken("]".to_string())),
+        Some(&Token::Open) => {
+            *pos += 1;
+            let mut items = Vec::new();
+            loop {
+                match tokens.get(*pos) {
+                    Some(&Token::Close) => {
+                        *pos += 1;
+                        break;
+                    }
+                    None => return Err(ParseError::UnmatchedBracket),
+                    Some(_) => items.push(try!(parse_expr(tokens, pos))),
+                }
+            }
+            if items.is_empty() {
+                return Err(ParseError::EmptyCell);
+            }
+            let last = items.pop().unwrap();
+            Ok(items.into_iter().rev().fold(last, |acc, item| Noun::new_cell(item, acc)))
+        }
+    }
+}
+
+/// Parses a complete surface-syntax program into a `Noun`.
+pub fn parse(input: &str) -> Result<Noun, ParseError> {
+    let mut lexer = Lexer::new(input);
+    let mut tokens = Vec::new();
+    while let Some(token) = try!(lexer.next_token()) {
+        tokens.push(token);
+    }
+
+    let mut pos = 0;
+    let noun = try!(parse_expr(&tokens, &mut pos));
+    if pos != tokens.len() {
+        return Err(ParseError::UnexpectedToken(format!("{:?}", tokens[pos])));
+    }
+    Ok(noun)
+}
+
+fn atom_to_decimal(bytes: &[u8]) -> String {
+    if bytes.iter().all(|&b| b == 0) {
+        return "0".to_string();
+    }
+
+    let mut remaining = bytes.to_vec();
+    let mut decimal_digits = Vec::new();
+    while !remaining.iter().all(|&b| b == 0) {
+        let mut remainder: u32 = 0;
+        for byte in remaining.iter_mut().rev() {
+            let cur = remainder * 256 + (*byte as u32);
+            *byte = (cur / 10) as u8;
+            remainder = cur % 10;
+        }
+        decimal_digits.push((b'0' + remainder as u8) as char);
+    }
+    decimal_digits.iter().rev().collect()
+}
+
+fn format_atom(f: &mut fmt::Formatter, bytes: &[u8]) -> fmt::Result {
+    if bytes.len() == 1 {
+        if let Some(name) = opcode_name(bytes[0]) {
+            return write!(f, "{}", name);
+        }
+    }
+    write!(f, "{}", atom_to_decimal(bytes))
+}
+
+fn write_cell_tail(f: &mut fmt::Formatter, right: &Noun) -> fmt::Result {
+    match right {
+        &Noun::Cell(ref left, ref right) => {
+            try!(write!(f, " {}", left));
+            write_cell_tail(f, right)
+        }
+        other => write!(f, " {}", other),
+    }
+}
+
+impl fmt::Display for Noun {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Noun::ByteAtom(b) => format_atom(f, &[b]),
+            &Noun::Atom(ref xs) => format_atom(f, &xs[..]),
+            &Noun::Cell(ref left, ref right) => {
+                try!(write!(f, "[{}", left));
+                try!(write_cell_tail(f, right));
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse;
+    use noun::Noun;
+
+    #[test]
+    fn parses_decimal_and_hex_atoms() {
+        assert_eq!(parse("44").unwrap(), Noun::from_u8(44));
+        assert_eq!(parse("0x2c").unwrap(), Noun::from_u8(44));
+        assert_eq!(parse("256").unwrap(), Noun::from_vec(vec![0x00, 0x01]));
+    }
+
+    #[test]
+    fn brackets_nest_right_associatively() {
+        assert_eq!(
+            parse("[1 2 3]").unwrap(),
+            Noun::new_cell(Noun::from_u8(1), Noun::new_cell(Noun::from_u8(2), Noun::from_u8(3)))
+        );
+    }
+
+    #[test]
+    fn opcode_mnemonics_map_to_their_numeric_value() {
+        assert_eq!(parse("AXIS").unwrap(), Noun::from_u8(0));
+        assert_eq!(parse("[LITERAL 44]").unwrap(), parse("[1 44]").unwrap());
+    }
+
+    #[test]
+    fn display_round_trips_through_parse() {
+        let noun = parse("[LITERAL [1 2 3]]").unwrap();
+        let printed = format!("{}", noun);
+        assert_eq!(parse(&printed).unwrap(), noun);
+    }
+
+    #[test]
+    fn unknown_symbol_is_an_error() {
+        assert!(parse("NOT_AN_OPCODE").is_err());
+    }
+
+    #[test]
+    fn check_pow_round_trips_through_parse() {
+        assert_eq!(parse("[CHECK_POW 1 2]").unwrap(), parse("[15 1 2]").unwrap());
+        let noun = parse("[CHECK_POW [1 2]]").unwrap();
+        let printed = format!("{}", noun);
+        assert_eq!(parse(&printed).unwrap(), noun);
+    }
+}